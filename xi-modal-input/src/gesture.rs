@@ -1,16 +1,86 @@
 //! Gesture (mouse) based movement
 
+use std::time::{Duration, Instant};
+
 use xi_core_lib::rpc::{GestureType, SelectionGranularity};
-use xi_core_lib::selection::{SelRegion, Selection};
+use xi_core_lib::selection::{HorizPos, SelRegion, Selection};
 use xi_core_lib::word_boundaries::WordCursor;
 use xi_rope::interval::IntervalBounds;
-use xi_rope::Rope;
+use xi_rope::{Cursor, Rope};
+
+/// Clicks within this many characters of the previous click count as
+/// landing "in the same spot" for the purposes of granularity escalation.
+const CLICK_MAX_DISTANCE: usize = 2;
+
+/// Default amount of time allowed between clicks for them to escalate the
+/// selection granularity, matching typical platform double-click timing.
+const CLICK_MAX_INTERVAL: Duration = Duration::from_millis(400);
+
+/// Tracks consecutive point clicks so that clicking repeatedly in
+/// (approximately) the same spot escalates the selection granularity, the
+/// way terminals turn a click/double-click/triple-click into a
+/// char/word/line selection.
+pub struct ClickTracker {
+    last_click: Option<(usize, Instant)>,
+    click_count: u32,
+    max_interval: Duration,
+}
+
+impl ClickTracker {
+    pub fn new() -> Self {
+        Self::with_interval(CLICK_MAX_INTERVAL)
+    }
+
+    /// Creates a `ClickTracker` with a custom threshold for how long
+    /// between clicks they still count as part of the same sequence,
+    /// overriding the `CLICK_MAX_INTERVAL` default.
+    pub fn with_interval(max_interval: Duration) -> Self {
+        ClickTracker { last_click: None, click_count: 0, max_interval }
+    }
+
+    /// Registers a click at `offset` and returns the granularity it
+    /// resolves to: 1st click is `Point`, 2nd is `Word`, 3rd and beyond
+    /// is `Line`.
+    fn track(&mut self, offset: usize) -> SelectionGranularity {
+        let now = Instant::now();
+        let is_continuation = self.last_click.is_some_and(|(last_offset, last_time)| {
+            let distance = offset.max(last_offset) - offset.min(last_offset);
+            distance <= CLICK_MAX_DISTANCE && now.duration_since(last_time) < self.max_interval
+        });
+
+        self.click_count = if is_continuation { self.click_count + 1 } else { 1 };
+        self.last_click = Some((offset, now));
+
+        match self.click_count {
+            1 => SelectionGranularity::Point,
+            2 => SelectionGranularity::Word,
+            _ => SelectionGranularity::Line,
+        }
+    }
+}
+
+impl Default for ClickTracker {
+    fn default() -> Self {
+        ClickTracker::new()
+    }
+}
 
 /// State required to resolve a drag gesture into a selection.
-pub(crate) struct DragState {
+pub struct DragState {
     /// All the selection regions other than the one being dragged.
     base_sel: Selection,
 
+    /// The raw offset the drag started at, independent of `min`/`max`
+    /// below (which may have been widened by click-escalated
+    /// granularity). This is what a block drag anchors its columns to.
+    ///
+    /// Not yet read anywhere: the only reader was the `BlockDrag` gesture
+    /// dispatch, dropped along with it pending upstream support (see
+    /// `block_selection`). Kept so that wiring is a one-line change once
+    /// that variant exists.
+    #[allow(dead_code)]
+    anchor: usize,
+
     /// Start of the region selected when drag was started (region is
     /// assumed to be forward).
     min: usize,
@@ -19,20 +89,57 @@ pub(crate) struct DragState {
     max: usize,
 
     granularity: SelectionGranularity,
+
+    /// The desired column of the drag: the anchor's column when the drag
+    /// starts, then the hit's column whenever a `Drag` hit lands short of
+    /// its line's end. It is kept while the drag crosses a blank or short
+    /// line, so coming back doesn't lose the column the user was aiming
+    /// for, mirroring vertical-move horizontal affinity.
+    /// `usize` to match `SelRegion`'s own `HorizPos`.
+    horiz: Option<HorizPos>,
 }
 
-pub(crate) fn region_for_gesture(
+/// Returns the column of `offset` on its line, i.e. the offset's distance
+/// from the start of the line.
+fn column_of_offset(text: &Rope, offset: usize) -> HorizPos {
+    let line = text.line_of_offset(offset);
+    offset - text.offset_of_line(line)
+}
+
+/// Returns the length of `line`, excluding its trailing newline if any.
+fn line_content_len(text: &Rope, line: usize) -> usize {
+    let start = text.offset_of_line(line);
+    let mut end = text.offset_of_line(line + 1);
+    if end > start && text.slice_to_cow(end - 1..end) == "\n" {
+        end -= 1;
+    }
+    end - start
+}
+
+/// Returns the offset on `line` at visual column `col`, clamped to the
+/// line's actual length.
+fn seek_to_column(text: &Rope, line: usize, col: HorizPos) -> usize {
+    let line_start = text.offset_of_line(line);
+    let line_len = line_content_len(text, line);
+    line_start + col.min(line_len)
+}
+
+pub fn region_for_gesture(
     text: &Rope,
     offset: usize,
     granularity: SelectionGranularity,
+    separators: Option<&[char]>,
 ) -> SelRegion {
     match granularity {
         SelectionGranularity::Point => SelRegion::caret(offset),
-        SelectionGranularity::Word => {
-            let mut word_cursor = WordCursor::new(text, offset);
-            let (start, end) = word_cursor.select_word();
-            SelRegion::new(start, end)
-        }
+        SelectionGranularity::Word => match separators {
+            Some(separators) => semantic_word_region(text, offset, separators),
+            None => {
+                let mut word_cursor = WordCursor::new(text, offset);
+                let (start, end) = word_cursor.select_word();
+                SelRegion::new(start, end)
+            }
+        },
         SelectionGranularity::Line => {
             let line = text.line_of_offset(offset);
             let start = text.offset_of_line(line);
@@ -42,6 +149,126 @@ pub(crate) fn region_for_gesture(
     }
 }
 
+/// Characters, beyond alphanumerics, that are valid within a URL per RFC
+/// 3986's `pchar`/`query`/`fragment` productions. A conservative
+/// superset that's sufficient for click-to-select.
+const URL_CHARS: &[char] = &[
+    '-', '.', '_', '~', ':', '/', '?', '#', '[', ']', '@', '!', '$', '&', '\'', '(', ')', '*',
+    '+', ',', ';', '=', '%',
+];
+
+/// Schemes recognized when validating a candidate URL span.
+const URL_SCHEMES: &[&str] = &["http://", "https://", "file://", "mailto:", "ftp://"];
+
+/// Finds the URL, if any, surrounding `offset` in `text`. Returns `None`
+/// if `offset` isn't inside a recognized URL, so callers can fall back
+/// to a different granularity.
+///
+/// Not yet wired into `region_for_gesture`: doing so needs a
+/// `SelectionGranularity::Url` variant, which doesn't exist in the
+/// published `xi-core-lib` this crate depends on. Kept (and tested) so
+/// it's ready to wire up once that variant lands upstream.
+#[allow(dead_code)]
+pub fn url_region_at(text: &Rope, offset: usize) -> Option<SelRegion> {
+    let is_url_char = |c: char| c.is_alphanumeric() || URL_CHARS.contains(&c);
+
+    let mut start = offset;
+    while let Some(c) = char_before(text, start) {
+        if !is_url_char(c) {
+            break;
+        }
+        start -= c.len_utf8();
+    }
+
+    let mut end = offset;
+    while let Some(c) = char_at(text, end) {
+        if !is_url_char(c) {
+            break;
+        }
+        end += c.len_utf8();
+    }
+
+    if start >= end {
+        return None;
+    }
+
+    let candidate = text.slice_to_cow(start..end);
+    let has_scheme =
+        URL_SCHEMES.iter().any(|scheme| candidate.starts_with(scheme)) || candidate.contains("://");
+    if !has_scheme {
+        return None;
+    }
+
+    // Trim trailing punctuation that's unlikely to be part of the URL,
+    // unless it's a closing paren balanced by an opening one in the span.
+    let mut trimmed_end = end;
+    while let Some(c) = char_before(text, trimmed_end) {
+        let should_trim = match c {
+            '.' | ',' => true,
+            ')' => {
+                let span = text.slice_to_cow(start..trimmed_end);
+                span.matches(')').count() > span.matches('(').count()
+            }
+            _ => false,
+        };
+        if !should_trim {
+            break;
+        }
+        trimmed_end -= c.len_utf8();
+    }
+
+    if trimmed_end <= start {
+        return None;
+    }
+
+    Some(SelRegion::new(start, trimmed_end))
+}
+
+/// Returns the character immediately preceding `offset`, or `None` at the
+/// start of the text.
+///
+/// Uses a `Cursor`, like `WordCursor` does, rather than re-slicing from
+/// the start of the buffer on every call, so scanning `k` characters
+/// backward costs O(k), not O(k * offset).
+fn char_before(text: &Rope, offset: usize) -> Option<char> {
+    Cursor::new(text, offset).prev_codepoint()
+}
+
+/// Returns the character at `offset`, or `None` at the end of the text.
+///
+/// Uses a `Cursor` rather than re-slicing to the end of the buffer on
+/// every call, so scanning `k` characters forward costs O(k), not
+/// O(k * (len - offset)).
+fn char_at(text: &Rope, offset: usize) -> Option<char> {
+    Cursor::new(text, offset).next_codepoint()
+}
+
+/// Scans outward from `offset`, treating whitespace and any character in
+/// `separators` as the boundary of the word. This lets callers override
+/// the default `WordCursor` notion of a word, for example to treat `/`,
+/// `.`, and `:` as non-breaking when selecting inside a path or URL.
+fn semantic_word_region(text: &Rope, offset: usize, separators: &[char]) -> SelRegion {
+    let is_boundary = |c: char| c.is_whitespace() || separators.contains(&c);
+
+    let mut start = offset;
+    while let Some(c) = char_before(text, start) {
+        if is_boundary(c) {
+            break;
+        }
+        start -= c.len_utf8();
+    }
+
+    let mut end = offset;
+    while let Some(c) = char_at(text, end) {
+        if is_boundary(c) {
+            break;
+        }
+        end += c.len_utf8();
+    }
+
+    SelRegion::new(start, end)
+}
+
 /// Calculates the region generated by extending (via shift-click or drag, e.g)
 /// an existing region.
 fn region_extending_region<IV: IntervalBounds>(
@@ -49,9 +276,10 @@ fn region_extending_region<IV: IntervalBounds>(
     active_region_interval: IV,
     offset: usize,
     granularity: SelectionGranularity,
+    separators: Option<&[char]>,
 ) -> SelRegion {
     let active = active_region_interval.into_interval(text.len());
-    let extension = region_for_gesture(text, offset, granularity);
+    let extension = region_for_gesture(text, offset, granularity, separators);
 
     if offset >= active.start {
         SelRegion::new(active.start, extension.end)
@@ -60,22 +288,180 @@ fn region_extending_region<IV: IntervalBounds>(
     }
 }
 
-pub(crate) struct GestureContext<'a> {
+/// Computes a rectangular (block/column) selection spanning the lines
+/// between `anchor` and `offset`, producing one `SelRegion` per line
+/// instead of a single contiguous span.
+///
+/// Not yet wired into `GestureContext::selection_for_gesture`: doing so
+/// needs a `GestureType::BlockDrag` variant, which doesn't exist in the
+/// published `xi-core-lib` this crate depends on. Kept (and tested) so
+/// it's ready to wire up once that variant lands upstream.
+#[allow(dead_code)]
+fn block_selection(text: &Rope, anchor: usize, offset: usize) -> Selection {
+    let anchor_line = text.line_of_offset(anchor);
+    let anchor_col = anchor - text.offset_of_line(anchor_line);
+    let active_line = text.line_of_offset(offset);
+    let active_col = offset - text.offset_of_line(active_line);
+
+    let (min_line, max_line) =
+        if anchor_line <= active_line { (anchor_line, active_line) } else { (active_line, anchor_line) };
+    let (start_col, end_col) =
+        if anchor_col <= active_col { (anchor_col, active_col) } else { (active_col, anchor_col) };
+
+    let mut sel = Selection::new();
+    for line in min_line..=max_line {
+        let line_start = text.offset_of_line(line);
+        let line_len = line_content_len(text, line);
+        if start_col > line_len {
+            // Line is too short to reach the start column; skip it.
+            continue;
+        }
+        let region_start = line_start + start_col;
+        let region_end = line_start + end_col.min(line_len);
+        sel.add_region(SelRegion::new(region_start, region_end));
+    }
+    sel
+}
+
+/// The bracket kinds considered by `expand_to_enclosing`, matched
+/// independently of one another.
+const BRACKET_PAIRS: [(char, char); 3] = [('(', ')'), ('[', ']'), ('{', '}')];
+
+/// Grows `region` outward to the nearest enclosing bracket pair, giving a
+/// lightweight structural selection without a full parser. Repeated
+/// invocations widen to the next pair out, emulating "select parent
+/// node." Returns `region` unchanged if no enclosing pair exists.
+///
+/// Not yet wired into `GestureContext::selection_for_gesture`: doing so
+/// needs a `GestureType::ExpandToEnclosing` variant, which doesn't exist
+/// in the published `xi-core-lib` this crate depends on. Kept (and
+/// tested) so it's ready to wire up once that variant lands upstream.
+#[allow(dead_code)]
+fn expand_to_enclosing(text: &Rope, region: SelRegion) -> SelRegion {
+    let start = region.min();
+    let end = region.max();
+
+    // Only step outside the bracket immediately to our left if `region`
+    // is *exactly* that bracket's interior (both edges line up with a
+    // matching pair) — otherwise this is a fresh selection that merely
+    // happens to abut an opening bracket (e.g. a caret placed right
+    // after `(`), and we want to select that immediate enclosing pair
+    // rather than skip past it to the next one out.
+    let scan_start = match char_before(text, start) {
+        Some(c) if BRACKET_PAIRS.iter().any(|(open, _)| *open == c) => {
+            let open_pos = start - c.len_utf8();
+            let close = BRACKET_PAIRS.iter().find(|(o, _)| *o == c).unwrap().1;
+            if find_matching_close(text, start, c, close) == Some(end) {
+                open_pos
+            } else {
+                start
+            }
+        }
+        _ => start,
+    };
+
+    let found = find_enclosing_open(text, scan_start).and_then(|(open_pos, open)| {
+        let close = BRACKET_PAIRS.iter().find(|(o, _)| *o == open).unwrap().1;
+        find_matching_close(text, open_pos + 1, open, close).map(|close_pos| (open_pos, close_pos))
+    });
+
+    match found {
+        Some((open_pos, close_pos)) => SelRegion::new(open_pos + 1, close_pos),
+        None => region,
+    }
+}
+
+/// Scans left from `from`, maintaining an unmatched-closer count per
+/// bracket kind, to find the nearest opening bracket whose pair isn't
+/// already closed within the scanned range. Brackets inside a quoted
+/// string (tracked with a simple toggling flag) are ignored.
+fn find_enclosing_open(text: &Rope, from: usize) -> Option<(usize, char)> {
+    let mut depths = [0i32; BRACKET_PAIRS.len()];
+    let mut in_string = false;
+    let mut pos = from;
+
+    while let Some(c) = char_before(text, pos) {
+        pos -= c.len_utf8();
+        if c == '"' {
+            in_string = !in_string;
+            continue;
+        }
+        if in_string {
+            continue;
+        }
+        for (i, (open, close)) in BRACKET_PAIRS.iter().enumerate() {
+            if c == *close {
+                depths[i] += 1;
+            } else if c == *open {
+                if depths[i] == 0 {
+                    return Some((pos, *open));
+                }
+                depths[i] -= 1;
+            }
+        }
+    }
+    None
+}
+
+/// Scans right from `from` for the `close` bracket matching `open`,
+/// honoring nested pairs of the same kind and ignoring brackets inside a
+/// quoted string.
+fn find_matching_close(text: &Rope, from: usize, open: char, close: char) -> Option<usize> {
+    let mut depth = 0i32;
+    let mut in_string = false;
+    let mut pos = from;
+
+    while let Some(c) = char_at(text, pos) {
+        if c == '"' {
+            in_string = !in_string;
+            pos += c.len_utf8();
+            continue;
+        }
+        if !in_string {
+            if c == close {
+                if depth == 0 {
+                    return Some(pos);
+                }
+                depth -= 1;
+            } else if c == open {
+                depth += 1;
+            }
+        }
+        pos += c.len_utf8();
+    }
+    None
+}
+
+pub struct GestureContext<'a> {
     text: &'a Rope,
     sel: &'a Selection,
     drag_state: &'a mut Option<DragState>,
+    click_tracker: &'a mut ClickTracker,
+
+    /// Custom word-granularity separators, e.g. to treat `/` and `:` as
+    /// non-breaking when selecting inside a path or URL. `None` falls
+    /// back to the default `WordCursor` behavior.
+    word_separators: Option<&'a [char]>,
 }
 
 impl<'a> GestureContext<'a> {
-    pub(crate) fn new(
+    pub fn new(
         text: &'a Rope,
         sel: &'a Selection,
         drag_state: &'a mut Option<DragState>,
+        click_tracker: &'a mut ClickTracker,
     ) -> Self {
-        GestureContext { text, sel, drag_state }
+        GestureContext { text, sel, drag_state, click_tracker, word_separators: None }
+    }
+
+    /// Overrides the default word-granularity boundary detection with a
+    /// custom set of separator characters.
+    pub fn with_word_separators(mut self, separators: &'a [char]) -> Self {
+        self.word_separators = Some(separators);
+        self
     }
 
-    pub(crate) fn selection_for_gesture(
+    pub fn selection_for_gesture(
         &mut self,
         offset: usize,
         gesture: GestureType,
@@ -93,55 +479,103 @@ impl<'a> GestureContext<'a> {
 
         match gesture {
             GestureType::Select { granularity, multi } => {
-                let new_region = region_for_gesture(&self.text, offset, granularity);
-                let new_sel = if multi {
-                    let mut new = self.sel.clone();
+                // A plain point click escalates through click/word/line
+                // granularity on repeated clicks in the same spot.
+                let granularity = if granularity == SelectionGranularity::Point {
+                    self.click_tracker.track(offset)
+                } else {
+                    granularity
+                };
+                let new_region =
+                    region_for_gesture(self.text, offset, granularity, self.word_separators);
+                // `base_sel` holds the regions that aren't being dragged,
+                // so that `Drag` can later add the in-progress region
+                // back without it merging into (and losing the `horiz`
+                // of) its own earlier snapshot.
+                let base_sel = if multi { self.sel.clone() } else { Selection::new() };
+                let new_sel = {
+                    let mut new = base_sel.clone();
                     new.add_region(new_region);
                     new
-                } else {
-                    new_region.into()
                 };
 
                 *(self.drag_state) = Some(DragState {
-                    base_sel: new_sel.clone(),
+                    base_sel,
+                    anchor: offset,
                     min: new_region.start,
                     max: new_region.end,
                     granularity,
+                    horiz: Some(column_of_offset(self.text, new_region.start)),
                 });
                 new_sel
             }
             GestureType::SelectExtend { granularity } => {
-                if self.sel.len() == 0 {
+                if self.sel.is_empty() {
                     return self.sel.clone();
                 }
-                let active_region = self.sel.last().clone().unwrap();
-                let new_region = region_for_gesture(self.text, offset, granularity);
+                let active_region = self.sel.last().unwrap();
+                let new_region =
+                    region_for_gesture(self.text, offset, granularity, self.word_separators);
                 let merged_region = if offset >= new_region.start {
                     SelRegion::new(active_region.start, new_region.end)
                 } else {
                     SelRegion::new(active_region.start, new_region.start)
                 };
-                let mut new = self.sel.clone();
+                // As in `Select`, `base_sel` excludes the region being
+                // extended so `Drag` can later replace it cleanly.
+                let mut base_sel = self.sel.clone();
+                base_sel.delete_range(active_region.min(), active_region.max(), true);
+                let mut new = base_sel.clone();
                 new.add_region(merged_region);
                 *(self.drag_state) = Some(DragState {
-                    base_sel: new.clone(),
+                    base_sel,
+                    anchor: offset,
                     min: new_region.start,
                     max: new_region.end,
                     granularity,
+                    horiz: Some(column_of_offset(self.text, active_region.start)),
                 });
 
                 new
             }
             GestureType::Drag => {
-                let new_sel = self.drag_state.as_ref().map(|drag_state| {
+                // Bind these ahead of `self.drag_state.as_mut()` so the
+                // closure below captures the references directly instead
+                // of `self` as a whole, which would conflict with the
+                // mutable borrow of `self.drag_state`.
+                let text = self.text;
+                let word_separators = self.word_separators;
+                let new_sel = self.drag_state.as_mut().map(|drag_state| {
                     let mut sel = drag_state.base_sel.clone();
+
+                    let target_line = text.line_of_offset(offset);
+                    let line_len = line_content_len(text, target_line);
+                    let natural_col = column_of_offset(text, offset);
+
+                    // If the raw hit lands short of the end of its line,
+                    // it reflects the mouse's true column, so adopt it as
+                    // the new desired column. Otherwise the hit sits at
+                    // its line's end (where a hit past the end of a short
+                    // line is clamped to), so seek to the preserved column,
+                    // clamped to this line's length, instead of the raw
+                    // offset — this happens even on a line long enough to
+                    // reach that column, and is what lets dragging back
+                    // onto a longer line restore the original column.
+                    let (target_offset, horiz) = match drag_state.horiz {
+                        Some(_) if natural_col < line_len => (offset, Some(natural_col)),
+                        Some(col) => (seek_to_column(text, target_line, col), Some(col)),
+                        None => (offset, None),
+                    };
+
                     let new_region = region_extending_region(
-                        &self.text,
+                        text,
                         drag_state.min..drag_state.max,
-                        offset,
+                        target_offset,
                         drag_state.granularity,
+                        word_separators,
                     );
-                    sel.add_region(new_region.with_horiz(None));
+                    drag_state.horiz = horiz;
+                    sel.add_region(new_region.with_horiz(horiz));
                     sel
                 });
 
@@ -150,4 +584,283 @@ impl<'a> GestureContext<'a> {
             _other => panic!("unexpected gesture type {:?}", _other),
         }
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn click_tracker_escalates_nearby_clicks() {
+        let mut tracker = ClickTracker::with_interval(Duration::from_millis(400));
+        assert_eq!(tracker.track(10), SelectionGranularity::Point);
+        assert_eq!(tracker.track(11), SelectionGranularity::Word);
+        assert_eq!(tracker.track(10), SelectionGranularity::Line);
+        // Further clicks in the same spot stay at Line.
+        assert_eq!(tracker.track(10), SelectionGranularity::Line);
+    }
+
+    #[test]
+    fn click_tracker_resets_on_distant_click() {
+        let mut tracker = ClickTracker::with_interval(Duration::from_millis(400));
+        assert_eq!(tracker.track(10), SelectionGranularity::Point);
+        assert_eq!(tracker.track(11), SelectionGranularity::Word);
+        assert_eq!(tracker.track(100), SelectionGranularity::Point);
+    }
+
+    #[test]
+    fn click_tracker_resets_after_interval_elapses() {
+        let mut tracker = ClickTracker::with_interval(Duration::from_millis(0));
+        assert_eq!(tracker.track(10), SelectionGranularity::Point);
+        // With a zero-length interval, any subsequent click is a miss.
+        assert_eq!(tracker.track(10), SelectionGranularity::Point);
+    }
+
+    #[test]
+    fn gesture_context_escalates_click_granularity_through_select() {
+        let text = Rope::from("foo bar baz");
+        let sel = Selection::new();
+        let mut drag_state = None;
+        let mut click_tracker = ClickTracker::with_interval(Duration::from_millis(400));
+        let mut ctx = GestureContext::new(&text, &sel, &mut drag_state, &mut click_tracker);
+        let gesture = GestureType::Select { granularity: SelectionGranularity::Point, multi: false };
+        let offset = 5; // inside "bar"
+
+        let first = ctx.selection_for_gesture(offset, gesture);
+        let region = first.last().unwrap();
+        assert_eq!(region.start, region.end, "first click is a caret");
+
+        let second = ctx.selection_for_gesture(offset, gesture);
+        let region = second.last().unwrap();
+        assert_eq!(text.slice_to_cow(region.start..region.end), "bar", "second click selects the word");
+
+        let third = ctx.selection_for_gesture(offset, gesture);
+        let region = third.last().unwrap();
+        assert_eq!(
+            text.slice_to_cow(region.start..region.end),
+            "foo bar baz",
+            "third click selects the whole line"
+        );
+    }
+
+    #[test]
+    fn block_selection_emits_one_region_per_line() {
+        let text = Rope::from("aaaa\nbb\ncccccc\n");
+        // Anchor at col 1 of line 0, dragging to col 3 of line 2.
+        let sel = block_selection(&text, 1, text.offset_of_line(2) + 3);
+        let regions: Vec<(usize, usize)> = sel.iter().map(|r| (r.start, r.end)).collect();
+        assert_eq!(
+            regions,
+            vec![
+                (1, 3),
+                // Line 1 ("bb") is too short to reach column 3, so it's
+                // clamped to its own length.
+                (text.offset_of_line(1) + 1, text.offset_of_line(1) + 2),
+                (text.offset_of_line(2) + 1, text.offset_of_line(2) + 3),
+            ]
+        );
+    }
+
+    #[test]
+    fn block_selection_skips_lines_shorter_than_start_col() {
+        let text = Rope::from("aaaa\n\ncccc\n");
+        // Anchor at col 2, dragging to col 2 two lines down; the blank
+        // middle line can't reach column 2 and should be skipped.
+        let sel = block_selection(&text, 2, text.offset_of_line(2) + 2);
+        assert_eq!(sel.len(), 2);
+    }
+
+    #[test]
+    fn semantic_word_region_treats_custom_chars_as_non_breaking() {
+        let text = Rope::from("visit /usr/local/bin please");
+        // Clicking in the middle of the path, with `/` configured as
+        // non-breaking, should select the whole path.
+        let offset = text.slice_to_cow(0..text.len()).find("local").unwrap() + 2;
+        let region = semantic_word_region(&text, offset, &[]);
+        assert_eq!(text.slice_to_cow(region.start..region.end), "/usr/local/bin");
+    }
+
+    #[test]
+    fn semantic_word_region_breaks_on_configured_separator() {
+        let text = Rope::from("foo.bar");
+        let region = semantic_word_region(&text, 1, &['.']);
+        assert_eq!(text.slice_to_cow(region.start..region.end), "foo");
+    }
+
+    #[test]
+    fn url_region_at_selects_whole_url() {
+        let text = Rope::from("see https://example.com/path for details");
+        let offset = text.slice_to_cow(0..text.len()).find("example").unwrap();
+        let region = url_region_at(&text, offset).unwrap();
+        assert_eq!(text.slice_to_cow(region.start..region.end), "https://example.com/path");
+    }
+
+    #[test]
+    fn url_region_at_trims_trailing_punctuation() {
+        let text = Rope::from("go to https://example.com/path, now");
+        let offset = text.slice_to_cow(0..text.len()).find("example").unwrap();
+        let region = url_region_at(&text, offset).unwrap();
+        assert_eq!(text.slice_to_cow(region.start..region.end), "https://example.com/path");
+    }
+
+    #[test]
+    fn url_region_at_trims_unbalanced_trailing_paren() {
+        let text = Rope::from("(see https://example.com/path) end");
+        let offset = text.slice_to_cow(0..text.len()).find("example").unwrap();
+        let region = url_region_at(&text, offset).unwrap();
+        assert_eq!(text.slice_to_cow(region.start..region.end), "https://example.com/path");
+    }
+
+    #[test]
+    fn url_region_at_keeps_balanced_parens_in_url() {
+        let text = Rope::from("see https://example.com/path(1)");
+        let offset = text.slice_to_cow(0..text.len()).find("example").unwrap();
+        let region = url_region_at(&text, offset).unwrap();
+        assert_eq!(text.slice_to_cow(region.start..region.end), "https://example.com/path(1)");
+    }
+
+    #[test]
+    fn url_region_at_returns_none_for_plain_text() {
+        let text = Rope::from("just some words");
+        assert!(url_region_at(&text, 2).is_none());
+    }
+
+    #[test]
+    fn gesture_context_honors_word_separators_on_select() {
+        let text = Rope::from("visit /usr/local/bin please");
+        let offset = text.slice_to_cow(0..text.len()).find("local").unwrap() + 2;
+        let gesture = GestureType::Select { granularity: SelectionGranularity::Word, multi: false };
+
+        // With the default `WordCursor` notion of a word (no custom
+        // separators), `/` breaks the selection.
+        let sel = Selection::new();
+        let mut drag_state = None;
+        let mut click_tracker = ClickTracker::new();
+        let mut ctx = GestureContext::new(&text, &sel, &mut drag_state, &mut click_tracker);
+        let default_sel = ctx.selection_for_gesture(offset, gesture);
+        let region = default_sel.last().unwrap();
+        assert_eq!(text.slice_to_cow(region.start..region.end), "local");
+
+        // Configuring `/` as non-breaking (i.e. not a separator) selects
+        // the whole path instead.
+        let sel = Selection::new();
+        let mut drag_state = None;
+        let mut click_tracker = ClickTracker::new();
+        let separators: [char; 0] = [];
+        let mut ctx = GestureContext::new(&text, &sel, &mut drag_state, &mut click_tracker)
+            .with_word_separators(&separators);
+        let custom_sel = ctx.selection_for_gesture(offset, gesture);
+        let region = custom_sel.last().unwrap();
+        assert_eq!(text.slice_to_cow(region.start..region.end), "/usr/local/bin");
+    }
+
+    #[test]
+    fn expand_to_enclosing_selects_immediate_pair_from_fresh_caret() {
+        let text = Rope::from("(x)");
+        // A caret right after `(`, not the result of a prior expand call.
+        let region = expand_to_enclosing(&text, SelRegion::caret(1));
+        assert_eq!(text.slice_to_cow(region.start..region.end), "x");
+    }
+
+    #[test]
+    fn expand_to_enclosing_selects_immediate_pair_mid_word() {
+        let text = Rope::from("foo(bar, baz)");
+        let offset = text.slice_to_cow(0..text.len()).find("bar").unwrap();
+        let region = expand_to_enclosing(&text, SelRegion::new(offset, offset + 3));
+        assert_eq!(text.slice_to_cow(region.start..region.end), "bar, baz");
+    }
+
+    #[test]
+    fn expand_to_enclosing_widens_to_next_pair_out_on_repeat() {
+        let text = Rope::from("((x))");
+        let inner = expand_to_enclosing(&text, SelRegion::caret(2));
+        assert_eq!(text.slice_to_cow(inner.start..inner.end), "x");
+        let outer = expand_to_enclosing(&text, inner);
+        assert_eq!(text.slice_to_cow(outer.start..outer.end), "(x)");
+        // No pair encloses the outermost one; stays unchanged.
+        let unchanged = expand_to_enclosing(&text, outer);
+        assert_eq!(unchanged.start, outer.start);
+        assert_eq!(unchanged.end, outer.end);
+    }
+
+    #[test]
+    fn expand_to_enclosing_respects_distinct_bracket_kinds() {
+        let text = Rope::from("[a(b)c]");
+        let offset = text.slice_to_cow(0..text.len()).find('b').unwrap();
+        let region = expand_to_enclosing(&text, SelRegion::caret(offset));
+        assert_eq!(text.slice_to_cow(region.start..region.end), "b");
+    }
+
+    #[test]
+    fn expand_to_enclosing_ignores_brackets_inside_quotes() {
+        let text = Rope::from("(\"(\")");
+        let offset = text.slice_to_cow(0..text.len()).find(')').unwrap();
+        // The `(` inside the quoted string should not be treated as an
+        // enclosing opener for the caret sitting right after the string.
+        let region = expand_to_enclosing(&text, SelRegion::caret(offset));
+        assert_eq!(text.slice_to_cow(region.start..region.end), "\"(\"");
+    }
+
+    #[test]
+    fn expand_to_enclosing_returns_unchanged_without_enclosing_pair() {
+        let text = Rope::from("no brackets here");
+        let region = SelRegion::caret(3);
+        let result = expand_to_enclosing(&text, region);
+        assert_eq!(result.start, region.start);
+        assert_eq!(result.end, region.end);
+    }
+
+    #[test]
+    fn column_of_offset_is_distance_from_line_start() {
+        let text = Rope::from("aaaa\nbb\ncccccc\n");
+        assert_eq!(column_of_offset(&text, 2), 2);
+        assert_eq!(column_of_offset(&text, text.offset_of_line(2) + 3), 3);
+    }
+
+    #[test]
+    fn line_content_len_excludes_trailing_newline() {
+        let text = Rope::from("aaaa\nbb\ncccccc");
+        assert_eq!(line_content_len(&text, 0), 4);
+        assert_eq!(line_content_len(&text, 1), 2);
+        // Last line has no trailing newline.
+        assert_eq!(line_content_len(&text, 2), 6);
+    }
+
+    #[test]
+    fn seek_to_column_clamps_to_line_length() {
+        let text = Rope::from("aaaa\nbb\ncccccc\n");
+        // Column 3 is reachable on line 0 ("aaaa").
+        assert_eq!(seek_to_column(&text, 0, 3), 3);
+        // Column 3 is out of range on line 1 ("bb"); clamps to its end.
+        assert_eq!(seek_to_column(&text, 1, 3), text.offset_of_line(1) + 2);
+    }
+
+    #[test]
+    fn gesture_context_restores_horiz_through_drag_after_blank_line() {
+        let text = Rope::from("aaaa\n\ncccccc\n");
+        let sel = Selection::new();
+        let mut drag_state = None;
+        let mut click_tracker = ClickTracker::new();
+        let mut ctx = GestureContext::new(&text, &sel, &mut drag_state, &mut click_tracker);
+
+        // Start the drag at column 3 of line 0.
+        ctx.selection_for_gesture(
+            3,
+            GestureType::Select { granularity: SelectionGranularity::Point, multi: false },
+        );
+
+        // Drag onto the blank line 1, which can only offer column 0; the
+        // preserved column should survive this clamp rather than being
+        // forgotten.
+        let mid = ctx.selection_for_gesture(text.offset_of_line(1), GestureType::Drag);
+        assert_eq!(mid.last().unwrap().end, text.offset_of_line(1));
+
+        // Dragging back onto the long line 2 restores column 3, even
+        // though the raw hit passed in lands at that line's own end.
+        let restored =
+            ctx.selection_for_gesture(text.offset_of_line(2) + 6, GestureType::Drag);
+        let region = restored.last().unwrap();
+        assert_eq!(region.end, text.offset_of_line(2) + 3);
+        assert_eq!(region.horiz, Some(3));
+    }
 }
\ No newline at end of file